@@ -1,13 +1,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 use eframe::egui;
-use eframe::egui::ColorImage;
 use egui::{
     menu,
-    plot::{self, Legend, Line, Plot, PlotPoint, PlotPoints, Points},
-    Color32, FontId, Layout, Pos2, Stroke, TextStyle, Vec2,
+    plot::{Line, Plot, PlotPoints},
+    Color32, FontId, Layout,
 };
-use std::{fs, path::PathBuf};
+use evalexpr::{build_operator_tree, ContextWithMutableVariables, HashMapContext, Node, Value as EvalValue};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fs, path::PathBuf};
 
 static POSSIBLE_COLORS: [Color32; 11] = [
     Color32::RED,
@@ -28,6 +29,9 @@ fn main() -> Result<(), eframe::Error> {
         drag_and_drop_support: true,
         initial_window_size: Some(egui::vec2(720.0, 640.0)),
         renderer: eframe::Renderer::Glow,
+        // Lets `export_png` clear to a zero-alpha background on request;
+        // CentralPanel's opaque frame covers it up the rest of the time.
+        transparent: true,
         ..Default::default()
     };
     eframe::run_native(
@@ -37,18 +41,91 @@ fn main() -> Result<(), eframe::Error> {
     )
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
 enum LineMode {
+    #[default]
     Normal,
     Derivative,
+    Expression,
 }
 
-impl Default for LineMode {
+// one plotted curve, as persisted to a session file
+#[derive(Serialize, Deserialize)]
+struct Layer {
+    name: String,
+    visible: bool,
+    points: Vec<(f64, f64, f64)>,
+    he: f64,
+    gamma: f64,
+    // raw expression text for this layer, if one was applied in Expression
+    // mode; the compiled `Node` itself isn't serializable, so it's rebuilt
+    // from this string on load
+    #[serde(default)]
+    expr: Option<String>,
+}
+
+// state saved by "Save Session" / restored by "Open Session"
+#[derive(Serialize, Deserialize)]
+struct Session {
+    layers: Vec<Layer>,
+    line_mode: LineMode,
+}
+
+// a header token to pull a scalar from: either the first one starting with
+// a keyword ("he=") or a fixed token index
+#[derive(Clone)]
+enum ScalarSource {
+    Keyword(String),
+    Index(usize),
+}
+
+#[derive(Clone)]
+struct ScalarMapping {
+    label: String,
+    source: ScalarSource,
+}
+
+// which header tokens are scalar params and which columns are x/y/y'; each
+// (y_col, yp_col) pair in series becomes its own layer
+#[derive(Clone)]
+struct ParserConfig {
+    scalars: Vec<ScalarMapping>,
+    x_col: usize,
+    series: Vec<(usize, usize)>,
+}
+
+impl Default for ParserConfig {
     fn default() -> Self {
-        LineMode::Normal
+        ParserConfig {
+            scalars: vec![
+                ScalarMapping {
+                    label: "he".into(),
+                    source: ScalarSource::Index(17),
+                },
+                ScalarMapping {
+                    label: "gamma".into(),
+                    source: ScalarSource::Index(20),
+                },
+            ],
+            x_col: 0,
+            series: vec![(1, 2)],
+        }
     }
 }
 
+fn parse_scalar(tokens: &[&str], mapping: &ScalarMapping) -> Result<f64, String> {
+    let raw = match &mapping.source {
+        ScalarSource::Keyword(kw) => tokens
+            .iter()
+            .find(|t| t.starts_with(kw.as_str()))
+            .map(|t| t[kw.len()..].trim_end_matches(',')),
+        ScalarSource::Index(i) => tokens.get(*i).map(|t| t.trim_end_matches(',')),
+    };
+    let raw = raw.ok_or_else(|| format!("header is missing scalar '{}'", mapping.label))?;
+    raw.parse::<f64>()
+        .map_err(|_| format!("couldn't parse scalar '{}' value '{raw}' as a number", mapping.label))
+}
+
 #[derive(Default)]
 struct MyApp {
     dropped_files: Vec<egui::DroppedFile>,
@@ -60,9 +137,518 @@ struct MyApp {
     line_mode: LineMode,
     should_reset_plot: bool,
     layer_names: Vec<String>,
+    // scalar header parameters, parallel to `points`
+    layer_he: Vec<f64>,
+    layer_gamma: Vec<f64>,
+    // raw expression text and its compiled form, per layer, so switching
+    // modes and back doesn't lose what the user typed
+    layer_expressions: Vec<Option<(String, Node)>>,
+    expr_input: String,
+    expr_error: Option<String>,
+    // fuzzy file browser state
+    browse_root: Option<PathBuf>,
+    dat_files: Vec<PathBuf>,
+    file_search: String,
+    load_queue: VecDeque<PathBuf>,
+    session_error: Option<String>,
+    // configurable header/column parsing
+    parser_config: ParserConfig,
+    show_parser_settings: bool,
+    parse_error: Option<String>,
+    // PNG/SVG export
+    export_width: u32,
+    export_height: u32,
+    export_transparent: bool,
+    show_export_settings: bool,
+    export_error: Option<String>,
+    // screen-space rect of the plot widget, captured after the last draw,
+    // so PNG export can clip the framebuffer read to just the figure
+    plot_rect: Option<egui::Rect>,
+}
+
+// binds x, y, yp, he, gamma and evaluates node against them
+fn eval_expr_point(node: &Node, he: f64, gamma: f64, point: (f64, f64, f64)) -> Result<f64, String> {
+    let mut context = HashMapContext::default();
+    context
+        .set_value("x".into(), EvalValue::Float(point.0))
+        .map_err(|e| e.to_string())?;
+    context
+        .set_value("y".into(), EvalValue::Float(point.1))
+        .map_err(|e| e.to_string())?;
+    context
+        .set_value("yp".into(), EvalValue::Float(point.2))
+        .map_err(|e| e.to_string())?;
+    context
+        .set_value("he".into(), EvalValue::Float(he))
+        .map_err(|e| e.to_string())?;
+    context
+        .set_value("gamma".into(), EvalValue::Float(gamma))
+        .map_err(|e| e.to_string())?;
+
+    node.eval_with_context(&context)
+        .map_err(|e| e.to_string())?
+        .as_float()
+        .map_err(|e| e.to_string())
+}
+
+fn collect_dat_files(dir: &std::path::Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dat_files(&path, out);
+        } else if path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("DAT"))
+            .unwrap_or(false)
+        {
+            out.push(path);
+        }
+    }
+}
+
+// subsequence match, case-insensitive; None if query doesn't match at all
+fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let cand_chars: Vec<char> = candidate.chars().collect();
+    let cand_lower: Vec<char> = candidate.to_lowercase().chars().collect();
+
+    let mut qi = 0;
+    let mut score = 0i64;
+    let mut matched = Vec::new();
+    let mut prev_match: Option<usize> = None;
+
+    for (ci, c) in cand_lower.iter().enumerate() {
+        if qi >= query.len() {
+            break;
+        }
+        if *c == query[qi] {
+            let mut bonus = 1;
+            if prev_match == Some(ci.wrapping_sub(1)) {
+                bonus += 5; // consecutive characters score higher
+            }
+            if ci == 0 || !cand_chars[ci - 1].is_alphanumeric() {
+                bonus += 3; // word-boundary hits score higher
+            }
+            score += bonus;
+            matched.push(ci);
+            prev_match = Some(ci);
+            qi += 1;
+        }
+    }
+
+    if qi == query.len() {
+        Some((score, matched))
+    } else {
+        None
+    }
+}
+
+fn highlight_job(text: &str, matched: &[usize]) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    for (i, c) in text.chars().enumerate() {
+        let color = if matched.contains(&i) {
+            Color32::YELLOW
+        } else {
+            Color32::GRAY
+        };
+        job.append(
+            &c.to_string(),
+            0.0,
+            egui::TextFormat {
+                color,
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
+
+// escape the five XML entities so layer names can't break the SVG markup
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// bounding box (min_x, max_x, min_y, max_y) over every curve's points
+fn svg_bounds(curves: &[(usize, Vec<(f64, f64)>)]) -> (f64, f64, f64, f64) {
+    let all_points = curves.iter().flat_map(|(_, v)| v.iter());
+    let min_x = all_points.clone().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_x = all_points.clone().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = all_points.clone().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = all_points.map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    (min_x, max_x, min_y, max_y)
+}
+
+// maps value from [min, min + span] to [lo, hi]
+fn svg_scale(value: f64, min: f64, span: f64, lo: f64, hi: f64) -> f64 {
+    lo + (value - min) / span * (hi - lo)
+}
+
+impl MyApp {
+    // one new layer per configured (y_col, yp_col) series; bad files set
+    // self.parse_error instead of panicking
+    fn load_dat_file(&mut self, path: &PathBuf) {
+        let sol = match fs::read_to_string(path) {
+            Ok(sol) => sol,
+            Err(e) => {
+                self.parse_error = Some(format!("{}: {e}", path.display()));
+                return;
+            }
+        };
+
+        let mut contents = sol.split('\n').collect::<Vec<&str>>();
+        if contents.is_empty() {
+            self.parse_error = Some(format!("{}: file is empty", path.display()));
+            return;
+        }
+        let header = contents.remove(0);
+        let header_tokens = header.split_whitespace().collect::<Vec<&str>>();
+
+        let mut scalars = Vec::new();
+        for mapping in self.parser_config.scalars.clone() {
+            match parse_scalar(&header_tokens, &mapping) {
+                Ok(v) => scalars.push((mapping.label, v)),
+                Err(e) => {
+                    self.parse_error = Some(format!("{}: header: {e}", path.display()));
+                    return;
+                }
+            }
+        }
+        let he_val = scalars.iter().find(|(l, _)| l == "he").map(|(_, v)| *v).unwrap_or(0.0);
+        let gamma_val = scalars.iter().find(|(l, _)| l == "gamma").map(|(_, v)| *v).unwrap_or(0.0);
+        let scalar_label = scalars
+            .iter()
+            .map(|(l, v)| format!("{l}={v}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut flname = path.file_name().unwrap().to_str().unwrap();
+        flname = flname.trim_end_matches(".DAT");
+
+        let config = self.parser_config.clone();
+        for (series_idx, &(y_col, yp_col)) in config.series.iter().enumerate() {
+            let mut series_points = Vec::with_capacity(contents.len());
+            for (line_no, line) in contents.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let tokens = line.split_ascii_whitespace().collect::<Vec<&str>>();
+                let needed = [config.x_col, y_col, yp_col];
+                let max_col = needed.iter().max().copied().unwrap_or(0);
+                if tokens.len() <= max_col {
+                    self.parse_error = Some(format!(
+                        "{}: line {} has only {} column(s), need column {}",
+                        path.display(),
+                        line_no + 2, // account for the removed header line, 1-indexed
+                        tokens.len(),
+                        max_col
+                    ));
+                    return;
+                }
+
+                let parse_col = |col: usize| -> Result<f64, String> {
+                    tokens[col].parse::<f64>().map_err(|_| {
+                        format!(
+                            "{}: line {}, column {}: couldn't parse '{}' as a number",
+                            path.display(),
+                            line_no + 2,
+                            col,
+                            tokens[col]
+                        )
+                    })
+                };
+
+                let (x, y, yp) = match (parse_col(config.x_col), parse_col(y_col), parse_col(yp_col)) {
+                    (Ok(x), Ok(y), Ok(yp)) => (x, y, yp),
+                    (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                        self.parse_error = Some(e);
+                        return;
+                    }
+                };
+
+                series_points.push((x, y, yp));
+            }
+
+            self.points.push(series_points);
+            let name = if config.series.len() > 1 {
+                format!("{flname} [{scalar_label}] series {series_idx}")
+            } else {
+                format!("{flname} {scalar_label}")
+            };
+            self.layer_names.push(name);
+            self.layer_he.push(he_val);
+            self.layer_gamma.push(gamma_val);
+            self.layer_expressions.push(None);
+            self.is_visible.push(true);
+            self.solutions_count += 1;
+        }
+
+        self.parse_error = None;
+    }
+
+    fn save_session(&self, path: &PathBuf) -> Result<(), String> {
+        let layers = (0..self.solutions_count)
+            .map(|i| Layer {
+                name: self.layer_names[i].clone(),
+                visible: self.is_visible[i],
+                points: self.points[i].clone(),
+                he: self.layer_he[i],
+                gamma: self.layer_gamma[i],
+                expr: self.layer_expressions[i].as_ref().map(|(text, _)| text.clone()),
+            })
+            .collect();
+        let session = Session {
+            layers,
+            line_mode: self.line_mode,
+        };
+        let json = serde_json::to_string_pretty(&session).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    fn load_session(&mut self, path: &PathBuf) -> Result<(), String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let session: Session = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+
+        self.points.clear();
+        self.layer_names.clear();
+        self.is_visible.clear();
+        self.layer_he.clear();
+        self.layer_gamma.clear();
+        self.layer_expressions.clear();
+
+        for layer in session.layers {
+            self.points.push(layer.points);
+            self.layer_names.push(layer.name);
+            self.is_visible.push(layer.visible);
+            self.layer_he.push(layer.he);
+            self.layer_gamma.push(layer.gamma);
+            self.layer_expressions.push(match layer.expr {
+                Some(text) => match build_operator_tree(&text) {
+                    Ok(node) => Some((text, node)),
+                    Err(_) => None,
+                },
+                None => None,
+            });
+        }
+
+        self.solutions_count = self.points.len();
+        if let Some((text, _)) = self.layer_expressions.iter().flatten().next() {
+            self.expr_input = text.clone();
+        }
+        self.line_mode = session.line_mode;
+        if self.line_mode == LineMode::Expression
+            && self.layer_expressions.iter().all(Option::is_none)
+        {
+            self.line_mode = LineMode::Normal;
+            self.session_error =
+                Some("session was saved in Expression mode but no expression could be restored; showing Flux instead".into());
+        }
+        self.should_reset_plot = true;
+        Ok(())
+    }
+
+    // glReadPixels only sees what's on screen, so this uses the plot's real
+    // pixel size rather than export_width/export_height (those only apply
+    // to the analytically-drawn SVG export)
+    fn export_png(&self, ctx: &egui::Context, frame: &eframe::Frame, path: &PathBuf) -> Result<(), String> {
+        use glow::HasContext as _;
+
+        let rect = self
+            .plot_rect
+            .ok_or_else(|| "no plot on screen yet to export".to_string())?;
+        let gl = frame.gl().ok_or_else(|| "no GL context available".to_string())?;
+
+        let ppp = ctx.pixels_per_point();
+        let x0 = (rect.min.x * ppp).round() as i32;
+        let y0 = (rect.min.y * ppp).round() as i32;
+        let w = (rect.width() * ppp).round() as u32;
+        let h = (rect.height() * ppp).round() as u32;
+
+        // OpenGL's origin is bottom-left, egui's is top-left, so the read
+        // starts at the rect's bottom edge: `y0 + h` pixels below the top of
+        // the framebuffer.
+        let gl_y0 = unsafe {
+            let mut viewport = [0i32; 4];
+            gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+            viewport[3] - y0 - h as i32
+        };
+
+        let mut pixels = vec![0u8; (w * h * 4) as usize];
+        unsafe {
+            gl.read_pixels(
+                x0,
+                gl_y0,
+                w as i32,
+                h as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut pixels),
+            );
+        }
+
+        // OpenGL's origin is bottom-left; flip rows so the PNG reads top-down.
+        let row_bytes = (w * 4) as usize;
+        let mut flipped = vec![0u8; pixels.len()];
+        for y in 0..h as usize {
+            let src = &pixels[y * row_bytes..(y + 1) * row_bytes];
+            let dst = h as usize - 1 - y;
+            flipped[dst * row_bytes..(dst + 1) * row_bytes].copy_from_slice(src);
+        }
+
+        if !self.export_transparent {
+            for px in flipped.chunks_exact_mut(4) {
+                px[3] = 255;
+            }
+        }
+
+        image::save_buffer(path, &flipped, w, h, image::ColorType::Rgba8).map_err(|e| e.to_string())
+    }
+
+    // same layer/point selection as the draw loop, emitted as an SVG
+    // polyline per visible layer plus axis ticks and a legend
+    fn export_svg(&self, path: &PathBuf) -> Result<(), String> {
+        let (w, h) = (self.export_width as f64, self.export_height as f64);
+        let margin = 48.0;
+
+        let mut curves: Vec<(usize, Vec<(f64, f64)>)> = Vec::new();
+        for sol in 0..self.solutions_count {
+            if !self.is_visible[sol] {
+                continue;
+            }
+            let values: Vec<(f64, f64)> = match self.line_mode {
+                LineMode::Normal => self.points[sol].iter().map(|p| (p.0, p.1)).collect(),
+                LineMode::Derivative => self.points[sol].iter().map(|p| (p.0, p.2)).collect(),
+                LineMode::Expression => {
+                    if let Some((_, node)) = &self.layer_expressions[sol] {
+                        self.points[sol]
+                            .iter()
+                            .filter_map(|p| {
+                                eval_expr_point(node, self.layer_he[sol], self.layer_gamma[sol], *p)
+                                    .ok()
+                                    .map(|v| (p.0, v))
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    }
+                }
+            };
+            curves.push((sol, values));
+        }
+
+        if curves.iter().all(|(_, v)| v.is_empty()) {
+            return Err("no visible layers to export".into());
+        }
+
+        let (min_x, max_x, min_y, max_y) = svg_bounds(&curves);
+        let (span_x, span_y) = ((max_x - min_x).max(1e-12), (max_y - min_y).max(1e-12));
+
+        let sx = |x: f64| svg_scale(x, min_x, span_x, margin, w - margin);
+        let sy = |y: f64| svg_scale(y, min_y, span_y, h - margin, margin);
+
+        let background = if self.export_transparent {
+            String::new()
+        } else {
+            format!(r#"<rect x="0" y="0" width="{w}" height="{h}" fill="white"/>"#)
+        };
+
+        let mut body = String::new();
+        for (sol, values) in &curves {
+            if values.is_empty() {
+                continue;
+            }
+            let color = POSSIBLE_COLORS[sol % POSSIBLE_COLORS.len()];
+            let hex = format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b());
+            let pts = values
+                .iter()
+                .map(|p| format!("{:.2},{:.2}", sx(p.0), sy(p.1)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            body.push_str(&format!(
+                r#"<polyline fill="none" stroke="{hex}" stroke-width="3" points="{pts}"/>"#
+            ));
+        }
+
+        const TICKS: usize = 5;
+        let mut axes = String::new();
+        axes.push_str(&format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="gray"/>"#,
+            margin,
+            h - margin,
+            w - margin,
+            h - margin
+        ));
+        axes.push_str(&format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="gray"/>"#,
+            margin,
+            margin,
+            margin,
+            h - margin
+        ));
+        for i in 0..=TICKS {
+            let t = i as f64 / TICKS as f64;
+            let x_val = min_x + t * span_x;
+            let y_val = min_y + t * span_y;
+            axes.push_str(&format!(
+                r#"<text x="{:.2}" y="{:.2}" font-size="10" fill="gray">{:.3}</text>"#,
+                sx(x_val),
+                h - margin + 14.0,
+                x_val
+            ));
+            axes.push_str(&format!(
+                r#"<text x="{:.2}" y="{:.2}" font-size="10" fill="gray">{:.3}</text>"#,
+                4.0,
+                sy(y_val),
+                y_val
+            ));
+        }
+
+        let mut legend = String::new();
+        for (i, (sol, _)) in curves.iter().enumerate() {
+            let color = POSSIBLE_COLORS[sol % POSSIBLE_COLORS.len()];
+            let hex = format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b());
+            let y = margin + i as f64 * 16.0;
+            legend.push_str(&format!(
+                r#"<rect x="{:.2}" y="{:.2}" width="10" height="10" fill="{hex}"/>"#,
+                w - margin - 160.0,
+                y
+            ));
+            legend.push_str(&format!(
+                r#"<text x="{:.2}" y="{:.2}" font-size="11" fill="black">{}</text>"#,
+                w - margin - 146.0,
+                y + 9.0,
+                xml_escape(&self.layer_names[*sol])
+            ));
+        }
+
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{background}{axes}{body}{legend}</svg>"#
+        );
+        fs::write(path, svg).map_err(|e| e.to_string())
+    }
 }
 
 impl eframe::App for MyApp {
+    // zero-alpha while "Transparent background" is checked, so export_png
+    // reads back real transparency; CentralPanel's frame below matches it
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        if self.export_transparent {
+            Color32::TRANSPARENT.to_normalized_gamma_f32()
+        } else {
+            visuals.panel_fill.to_normalized_gamma_f32()
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         egui::SidePanel::right("layers_panel")
             .resizable(false)
@@ -76,7 +662,7 @@ impl eframe::App for MyApp {
                             family: egui::FontFamily::Proportional,
                         };
                         ui.label(
-                            egui::RichText::new(format!("{}", self.layer_names[sol]))
+                            egui::RichText::new(self.layer_names[sol].to_string())
                                 .color(POSSIBLE_COLORS[sol % POSSIBLE_COLORS.len()])
                                 .font(fnt),
                         );
@@ -88,6 +674,9 @@ impl eframe::App for MyApp {
                             self.points.remove(sol);
                             self.is_visible.remove(sol);
                             self.layer_names.remove(sol);
+                            self.layer_he.remove(sol);
+                            self.layer_gamma.remove(sol);
+                            self.layer_expressions.remove(sol);
                             self.solutions_count -= 1;
                             should_break = true;
                         }
@@ -99,7 +688,170 @@ impl eframe::App for MyApp {
                 }
             });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
+        egui::SidePanel::left("file_browser_panel")
+            .resizable(true)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.heading("Files");
+                if ui.button("Choose folder...").clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        self.dat_files.clear();
+                        collect_dat_files(&dir, &mut self.dat_files);
+                        self.browse_root = Some(dir);
+                    }
+                }
+                if let Some(root) = &self.browse_root {
+                    ui.label(format!("{} ({} files)", root.display(), self.dat_files.len()));
+                }
+
+                let search_response = ui.text_edit_singleline(&mut self.file_search);
+
+                let mut scored: Vec<(i64, Vec<usize>, PathBuf)> = self
+                    .dat_files
+                    .iter()
+                    .filter_map(|p| {
+                        let name = p.file_name()?.to_str()?;
+                        let (score, matched) = fuzzy_match(&self.file_search, name)?;
+                        Some((score, matched, p.clone()))
+                    })
+                    .collect();
+                scored.sort_by_key(|b| std::cmp::Reverse(b.0));
+
+                let enter_pressed = search_response.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Load all filtered").clicked() {
+                        for (_, _, path) in &scored {
+                            self.load_queue.push_back(path.clone());
+                        }
+                    }
+                    ui.label(format!("{} matches", scored.len()));
+                });
+
+                if enter_pressed {
+                    if let Some((_, _, path)) = scored.first() {
+                        self.load_queue.push_back(path.clone());
+                    }
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (_, matched, path) in &scored {
+                        let name = path.file_name().unwrap().to_str().unwrap();
+                        let job = highlight_job(name, matched);
+                        if ui.selectable_label(false, job).clicked() {
+                            self.load_queue.push_back(path.clone());
+                        }
+                    }
+                });
+            });
+
+        let mut show_parser_settings = self.show_parser_settings;
+        egui::Window::new("Parser Settings")
+            .open(&mut show_parser_settings)
+            .show(ctx, |ui| {
+                ui.label("Scalar header parameters (matched by keyword or header token index):");
+                let mut remove_scalar = None;
+                for (i, mapping) in self.parser_config.scalars.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label("label");
+                        ui.text_edit_singleline(&mut mapping.label);
+
+                        let mut is_index = matches!(mapping.source, ScalarSource::Index(_));
+                        egui::ComboBox::from_id_source(format!("scalar_source_{i}"))
+                            .selected_text(if is_index { "index" } else { "keyword" })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut is_index, false, "keyword");
+                                ui.selectable_value(&mut is_index, true, "index");
+                            });
+                        match (is_index, &mapping.source) {
+                            (true, ScalarSource::Keyword(_)) => {
+                                mapping.source = ScalarSource::Index(0);
+                            }
+                            (false, ScalarSource::Index(_)) => {
+                                mapping.source = ScalarSource::Keyword(format!("{}=", mapping.label));
+                            }
+                            _ => {}
+                        }
+
+                        match &mut mapping.source {
+                            ScalarSource::Keyword(kw) => {
+                                ui.label("keyword");
+                                ui.text_edit_singleline(kw);
+                            }
+                            ScalarSource::Index(idx) => {
+                                ui.label("index");
+                                ui.add(egui::DragValue::new(idx));
+                            }
+                        }
+
+                        if ui.button("X").clicked() {
+                            remove_scalar = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove_scalar {
+                    self.parser_config.scalars.remove(i);
+                }
+                if ui.button("Add scalar").clicked() {
+                    self.parser_config.scalars.push(ScalarMapping {
+                        label: "param".into(),
+                        source: ScalarSource::Keyword("param=".into()),
+                    });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("x column index");
+                    ui.add(egui::DragValue::new(&mut self.parser_config.x_col));
+                });
+
+                ui.separator();
+                ui.label("y / y' column series (each pair becomes its own layer):");
+                let mut remove_series = None;
+                for (i, (y_col, yp_col)) in self.parser_config.series.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label("y column");
+                        ui.add(egui::DragValue::new(y_col));
+                        ui.label("y' column");
+                        ui.add(egui::DragValue::new(yp_col));
+                        if ui.button("X").clicked() {
+                            remove_series = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove_series {
+                    self.parser_config.series.remove(i);
+                }
+                if ui.button("Add series").clicked() {
+                    self.parser_config.series.push((1, 2));
+                }
+            });
+        self.show_parser_settings = show_parser_settings;
+
+        if self.export_width == 0 || self.export_height == 0 {
+            self.export_width = 1280;
+            self.export_height = 720;
+        }
+        let mut show_export_settings = self.show_export_settings;
+        egui::Window::new("Export Settings")
+            .open(&mut show_export_settings)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("width");
+                    ui.add(egui::DragValue::new(&mut self.export_width).clamp_range(1..=8192));
+                    ui.label("height");
+                    ui.add(egui::DragValue::new(&mut self.export_height).clamp_range(1..=8192));
+                });
+                ui.checkbox(&mut self.export_transparent, "Transparent background");
+            });
+        self.show_export_settings = show_export_settings;
+
+        let mut central_frame = egui::Frame::central_panel(&ctx.style());
+        if self.export_transparent {
+            central_frame = central_frame.fill(Color32::TRANSPARENT);
+        }
+        egui::CentralPanel::default().frame(central_frame).show(ctx, |ui| {
             menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
                     if ui.button("Open").clicked() {
@@ -113,6 +865,39 @@ impl eframe::App for MyApp {
                         self.solutions_count = 0;
                         self.is_visible.clear();
                         self.layer_names.clear();
+                        self.layer_he.clear();
+                        self.layer_gamma.clear();
+                        self.layer_expressions.clear();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Parser Settings").clicked() {
+                        self.show_parser_settings = true;
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Save Session").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Session", &["json"])
+                            .save_file()
+                        {
+                            if let Err(e) = self.save_session(&path) {
+                                self.session_error = Some(e);
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Open Session").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Session", &["json"])
+                            .pick_file()
+                        {
+                            if let Err(e) = self.load_session(&path) {
+                                self.session_error = Some(e);
+                            }
+                        }
                         ui.close_menu();
                     }
 
@@ -120,8 +905,47 @@ impl eframe::App for MyApp {
                         frame.close();
                     }
                 });
+
+                ui.menu_button("Export", |ui| {
+                    if ui.button("Export Settings").clicked() {
+                        self.show_export_settings = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export PNG...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("PNG", &["png"])
+                            .save_file()
+                        {
+                            if let Err(e) = self.export_png(ctx, frame, &path) {
+                                self.export_error = Some(e);
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Export SVG...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("SVG", &["svg"])
+                            .save_file()
+                        {
+                            if let Err(e) = self.export_svg(&path) {
+                                self.export_error = Some(e);
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                });
             });
 
+            if let Some(err) = &self.session_error {
+                ui.colored_label(Color32::RED, format!("Session error: {err}"));
+            }
+            if let Some(err) = &self.parse_error {
+                ui.colored_label(Color32::RED, format!("Parse error: {err}"));
+            }
+            if let Some(err) = &self.export_error {
+                ui.colored_label(Color32::RED, format!("Export error: {err}"));
+            }
+
             ui.with_layout(Layout::left_to_right(egui::Align::TOP), |ui| {
                 if ui
                     .radio_value(&mut self.line_mode, LineMode::Normal, "Flux")
@@ -135,42 +959,43 @@ impl eframe::App for MyApp {
                 {
                     self.should_reset_plot = true;
                 }
+                if ui
+                    .radio_value(&mut self.line_mode, LineMode::Expression, "Expression")
+                    .clicked()
+                {
+                    self.should_reset_plot = true;
+                }
             });
 
-            if let Some(picked_path) = &self.picked_path {
-                let sol = fs::read_to_string(picked_path);
-                if sol.is_ok() {
-                    let sol = sol.unwrap();
-                    let mut contents = sol.split('\n').collect::<Vec<&str>>();
-                    contents.remove(0);
-
-                    let he = contents[0].split_whitespace().collect::<Vec<&str>>()[17]
-                        .trim_end_matches(',');
-                    let gamma = contents[0].split_whitespace().collect::<Vec<&str>>()[20]
-                        .trim_end_matches(',');
-
-                    self.points.push(Vec::new());
-                    for x in 0..contents.len() - 1 {
-                        let line = contents[x].split_ascii_whitespace().collect::<Vec<&str>>();
-                        let (x, y, yp) = (line[0], line[1], line[2]);
-                        let (x, y, yp) = (
-                            x.parse::<f64>().unwrap(),
-                            y.parse::<f64>().unwrap(),
-                            yp.parse::<f64>().unwrap(),
-                        );
-
-                        self.points[self.solutions_count].push((x, y, yp));
+            if self.line_mode == LineMode::Expression {
+                ui.with_layout(Layout::left_to_right(egui::Align::TOP), |ui| {
+                    ui.label("f(x, y, yp, he, gamma) =");
+                    ui.text_edit_singleline(&mut self.expr_input);
+                    if ui.button("Apply").clicked() {
+                        match build_operator_tree(&self.expr_input) {
+                            Ok(node) => {
+                                self.expr_error = None;
+                                for sol in 0..self.solutions_count {
+                                    self.layer_expressions[sol] =
+                                        Some((self.expr_input.clone(), node.clone()));
+                                }
+                                self.should_reset_plot = true;
+                            }
+                            Err(e) => self.expr_error = Some(e.to_string()),
+                        }
                     }
+                });
+                if let Some(err) = &self.expr_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+            }
 
-                    let mut flname = picked_path.file_name().unwrap().to_str().unwrap();
-                    flname = flname.trim_end_matches(".DAT");
+            if let Some(picked_path) = self.picked_path.take() {
+                self.load_dat_file(&picked_path);
+            }
 
-                    self.layer_names
-                        .push(format!("{} he={} gamma={}", flname, he, gamma));
-                    self.is_visible.push(true);
-                    self.picked_path = None;
-                    self.solutions_count += 1;
-                }
+            while let Some(queued_path) = self.load_queue.pop_front() {
+                self.load_dat_file(&queued_path);
             }
 
             let plot_space = Plot::new("Plot")
@@ -183,7 +1008,7 @@ impl eframe::App for MyApp {
                 .allow_scroll(false);
 
             if self.should_reset_plot {
-                plot_space.reset().show(ui, |plot_ui| {
+                let plot_response = plot_space.reset().show(ui, |plot_ui| {
                     for sol in 0..self.solutions_count {
                         if !self.is_visible[sol] {
                             continue;
@@ -197,6 +1022,20 @@ impl eframe::App for MyApp {
                             LineMode::Derivative => {
                                 pl = self.points[sol].iter().map(|i| [i.0, i.2]).collect();
                             }
+                            LineMode::Expression => {
+                                if let Some((_, node)) = &self.layer_expressions[sol] {
+                                    pl = self.points[sol]
+                                        .iter()
+                                        .filter_map(|p| {
+                                            eval_expr_point(node, self.layer_he[sol], self.layer_gamma[sol], *p)
+                                                .ok()
+                                                .map(|v| [p.0, v])
+                                        })
+                                        .collect();
+                                } else {
+                                    pl = PlotPoints::default();
+                                }
+                            }
                         }
 
                         let line = Line::new(pl)
@@ -206,10 +1045,11 @@ impl eframe::App for MyApp {
                         plot_ui.line(line);
                     }
                 });
+                self.plot_rect = Some(plot_response.response.rect);
 
                 self.should_reset_plot = false;
             } else {
-                plot_space.show(ui, |plot_ui| {
+                let plot_response = plot_space.show(ui, |plot_ui| {
                     for sol in 0..self.solutions_count {
                         if !self.is_visible[sol] {
                             continue;
@@ -223,6 +1063,20 @@ impl eframe::App for MyApp {
                             LineMode::Derivative => {
                                 pl = self.points[sol].iter().map(|i| [i.0, i.2]).collect();
                             }
+                            LineMode::Expression => {
+                                if let Some((_, node)) = &self.layer_expressions[sol] {
+                                    pl = self.points[sol]
+                                        .iter()
+                                        .filter_map(|p| {
+                                            eval_expr_point(node, self.layer_he[sol], self.layer_gamma[sol], *p)
+                                                .ok()
+                                                .map(|v| [p.0, v])
+                                        })
+                                        .collect();
+                                } else {
+                                    pl = PlotPoints::default();
+                                }
+                            }
                         }
 
                         let line = Line::new(pl)
@@ -232,47 +1086,15 @@ impl eframe::App for MyApp {
                         plot_ui.line(line);
                     }
                 });
+                self.plot_rect = Some(plot_response.response.rect);
             }
         });
 
         if !self.dropped_files.is_empty() {
-            for file in &self.dropped_files {
-                let mut info = if let Some(path) = &file.path {
-                    let sol = fs::read_to_string(path);
-                    if sol.is_ok() {
-                        let sol = sol.unwrap();
-                        let mut contents = sol.split('\n').collect::<Vec<&str>>();
-                        contents.remove(0);
-
-                        let he = contents[0].split_whitespace().collect::<Vec<&str>>()[17]
-                            .trim_end_matches(',');
-                        let gamma = contents[0].split_whitespace().collect::<Vec<&str>>()[20]
-                            .trim_end_matches(',');
-
-                        self.points.push(Vec::new());
-                        for x in 0..contents.len() - 1 {
-                            let line = contents[x].split_ascii_whitespace().collect::<Vec<&str>>();
-                            let (x, y, yp) = (line[0], line[1], line[2]);
-                            let (x, y, yp) = (
-                                x.parse::<f64>().unwrap(),
-                                y.parse::<f64>().unwrap(),
-                                yp.parse::<f64>().unwrap(),
-                            );
-
-                            self.points[self.solutions_count].push((x, y, yp));
-                            self.is_visible.push(true);
-                        }
-
-                        let mut flname = path.file_name().unwrap().to_str().unwrap();
-                        flname = flname.trim_end_matches(".DAT");
-
-                        self.layer_names
-                            .push(format!("{} he={} gamma={}", flname, he, gamma));
-                        self.is_visible.push(true);
-                        self.picked_path = None;
-                        self.solutions_count += 1;
-                    }
-                };
+            for file in self.dropped_files.clone() {
+                if let Some(path) = &file.path {
+                    self.load_dat_file(path);
+                }
             }
         }
 
@@ -286,3 +1108,85 @@ impl eframe::App for MyApp {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_empty_query_matches_everything() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, Vec::new())));
+    }
+
+    #[test]
+    fn fuzzy_match_requires_in_order_subsequence() {
+        assert!(fuzzy_match("abc", "acb").is_none());
+        assert!(fuzzy_match("abc", "a1b2c3").is_some());
+    }
+
+    #[test]
+    fn fuzzy_match_scores_consecutive_runs_higher_than_scattered() {
+        let (scattered, _) = fuzzy_match("ab", "a__b").unwrap();
+        let (consecutive, _) = fuzzy_match("ab", "ab__").unwrap();
+        assert!(consecutive > scattered);
+    }
+
+    #[test]
+    fn fuzzy_match_no_match_returns_none() {
+        assert!(fuzzy_match("xyz", "abc").is_none());
+    }
+
+    #[test]
+    fn parse_scalar_by_index() {
+        let mapping = ScalarMapping {
+            label: "he".into(),
+            source: ScalarSource::Index(1),
+        };
+        let tokens = ["foo", "3.5,"];
+        assert_eq!(parse_scalar(&tokens, &mapping), Ok(3.5));
+    }
+
+    #[test]
+    fn parse_scalar_by_keyword() {
+        let mapping = ScalarMapping {
+            label: "gamma".into(),
+            source: ScalarSource::Keyword("gamma=".into()),
+        };
+        let tokens = ["foo", "gamma=0.2,"];
+        assert_eq!(parse_scalar(&tokens, &mapping), Ok(0.2));
+    }
+
+    #[test]
+    fn parse_scalar_missing_is_an_error() {
+        let mapping = ScalarMapping {
+            label: "he".into(),
+            source: ScalarSource::Index(5),
+        };
+        assert!(parse_scalar(&["a", "b"], &mapping).is_err());
+    }
+
+    #[test]
+    fn parse_scalar_non_numeric_is_an_error() {
+        let mapping = ScalarMapping {
+            label: "he".into(),
+            source: ScalarSource::Index(0),
+        };
+        assert!(parse_scalar(&["not-a-number"], &mapping).is_err());
+    }
+
+    #[test]
+    fn svg_bounds_covers_all_curves() {
+        let curves = vec![
+            (0, vec![(0.0, 1.0), (2.0, -1.0)]),
+            (1, vec![(-1.0, 5.0)]),
+        ];
+        assert_eq!(svg_bounds(&curves), (-1.0, 2.0, -1.0, 5.0));
+    }
+
+    #[test]
+    fn svg_scale_maps_endpoints() {
+        assert_eq!(svg_scale(0.0, 0.0, 10.0, 100.0, 200.0), 100.0);
+        assert_eq!(svg_scale(10.0, 0.0, 10.0, 100.0, 200.0), 200.0);
+        assert_eq!(svg_scale(5.0, 0.0, 10.0, 100.0, 200.0), 150.0);
+    }
+}